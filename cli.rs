@@ -0,0 +1,87 @@
+use clap::Parser;
+
+use crate::coupon::CouponDurationKind;
+use crate::export::OutputFormat;
+
+/// Command-line options for scripted/unattended runs.
+///
+/// Fields that identify *what* to create (stripe key, coupon name, expiry,
+/// count, product) fall back to an interactive prompt when left unset, so
+/// running with no arguments keeps today's UX. Fields that configure the
+/// discount/restrictions instead fall back to a sensible default (e.g.
+/// `once` duration, no minimum cart value) rather than prompting, so a
+/// scripted run is never blocked on stdin waiting for optional input.
+#[derive(Parser, Debug)]
+#[command(name = "rusty-voucher", about = "Generates Stripe promotion codes")]
+pub struct Cli {
+    /// Stripe secret key. Falls back to the STRIPE_API_KEY environment variable.
+    #[arg(long, env = "STRIPE_API_KEY")]
+    pub stripe_api_key: Option<String>,
+
+    /// Name of the coupon to create.
+    #[arg(long)]
+    pub coupon_name: Option<String>,
+
+    /// Expiration date for the coupon and its promotion codes (YYYY-MM-DD).
+    #[arg(long)]
+    pub expires: Option<String>,
+
+    /// Number of promotion codes to generate.
+    #[arg(long)]
+    pub count: Option<i32>,
+
+    /// Stripe product ID the coupon applies to.
+    #[arg(long)]
+    pub product_id: Option<String>,
+
+    /// Substring to filter the product list by name (case-insensitive).
+    #[arg(long)]
+    pub product_name: Option<String>,
+
+    /// Percentage off for the coupon (e.g. 100 for a free product). Mutually
+    /// exclusive with `--amount-off`.
+    #[arg(long, conflicts_with = "amount_off")]
+    pub percent_off: Option<f32>,
+
+    /// Fixed amount off for the coupon, in the smallest currency unit (e.g.
+    /// cents). Requires `--currency`. Mutually exclusive with `--percent-off`.
+    #[arg(long)]
+    pub amount_off: Option<i64>,
+
+    /// Currency for `--amount-off` and `--minimum-amount` (e.g. usd).
+    #[arg(long)]
+    pub currency: Option<String>,
+
+    /// Minimum cart value required to redeem a promotion code, in the
+    /// smallest currency unit. Requires `--currency`.
+    #[arg(long)]
+    pub minimum_amount: Option<i64>,
+
+    /// How long the coupon's discount applies once redeemed. Defaults to
+    /// `once`. `repeating` requires `--duration-in-months`.
+    #[arg(long, value_enum)]
+    pub duration: Option<CouponDurationKind>,
+
+    /// Number of months the discount repeats for. Required when
+    /// `--duration repeating` is set.
+    #[arg(long)]
+    pub duration_in_months: Option<i32>,
+
+    /// Maximum number of times the coupon itself can be redeemed across all
+    /// promotion codes. Unbounded if unset.
+    #[arg(long)]
+    pub max_redemptions: Option<i32>,
+
+    /// Restrict promotion codes generated from this coupon to customers
+    /// redeeming for the first time.
+    #[arg(long)]
+    pub first_time_transaction: bool,
+
+    /// Path to write the generated vouchers to.
+    #[arg(long, default_value = "vouchers.txt")]
+    pub output: String,
+
+    /// Format of the output file.
+    #[arg(long, value_enum, default_value = "plaintext")]
+    pub format: OutputFormat,
+}
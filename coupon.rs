@@ -0,0 +1,157 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Whether a coupon can actually be redeemed right now, derived from
+/// Stripe's `valid` flag and `redeem_by` date rather than trusting `valid`
+/// alone (Stripe only flips `valid` to `false` lazily).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CouponStatus {
+    Valid,
+    Expired,
+    Invalidated,
+}
+
+/// The discount a coupon applies. Stripe encodes these as mutually exclusive
+/// `percent_off` / (`amount_off` + `currency`) form fields, so the two
+/// shapes are modeled as variants rather than a pile of `Option`s.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DiscountValue {
+    Percentage { percent_off: f32 },
+    AmountOff { amount_off: i64, currency: String },
+}
+
+/// How long a coupon's discount applies once redeemed, mirroring Stripe's
+/// `duration` / `duration_in_months` fields.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "duration", rename_all = "snake_case")]
+pub enum CouponDuration {
+    Once,
+    Repeating { duration_in_months: i32 },
+    Forever,
+}
+
+/// CLI-selectable counterpart to [`CouponDuration`]. `Repeating` carries no
+/// data here since clap can't parse a `ValueEnum` variant's payload directly
+/// — the caller reads `--duration-in-months` separately and pairs it with
+/// this when building a `CouponDuration`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum CouponDurationKind {
+    Once,
+    Repeating,
+    Forever,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CouponRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub discount: DiscountValue,
+    #[serde(flatten)]
+    pub duration: CouponDuration,
+    pub max_redemptions: Option<i32>,
+    pub redeem_by: i64,
+    pub applies_to: CouponAppliesTo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CouponAppliesTo {
+    pub products: Vec<String>,
+}
+
+/// Optional restrictions on when a promotion code can be redeemed, mapped
+/// onto Stripe's `restrictions[*]` form keys.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PromoCodeRequirements {
+    pub first_time_transaction: bool,
+    pub minimum_amount: Option<i64>,
+    pub minimum_amount_currency: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PromotionCodeRequest {
+    pub coupon: String,
+    pub code: String,
+    pub expires_at: i64,
+    pub max_redemptions: i32,
+    pub restrictions: PromoCodeRequirements,
+}
+
+/// Stripe's response to a successful promotion code creation. Only the
+/// fields we rely on are modeled, but they must be required (not `Option`)
+/// so an error envelope — which has none of them — fails to deserialize as
+/// this type instead of silently matching it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PromotionCode {
+    pub id: String,
+    pub code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_percentage_discount() {
+        let discount = DiscountValue::Percentage { percent_off: 50.0 };
+        assert_eq!(serde_qs::to_string(&discount).unwrap(), "percent_off=50");
+    }
+
+    #[test]
+    fn serializes_amount_off_discount() {
+        let discount = DiscountValue::AmountOff {
+            amount_off: 500,
+            currency: "usd".to_owned(),
+        };
+        assert_eq!(
+            serde_qs::to_string(&discount).unwrap(),
+            "amount_off=500&currency=usd"
+        );
+    }
+
+    #[test]
+    fn serializes_once_duration() {
+        let duration = CouponDuration::Once;
+        assert_eq!(serde_qs::to_string(&duration).unwrap(), "duration=once");
+    }
+
+    #[test]
+    fn serializes_repeating_duration() {
+        let duration = CouponDuration::Repeating {
+            duration_in_months: 3,
+        };
+        assert_eq!(
+            serde_qs::to_string(&duration).unwrap(),
+            "duration=repeating&duration_in_months=3"
+        );
+    }
+
+    #[test]
+    fn serializes_forever_duration() {
+        let duration = CouponDuration::Forever;
+        assert_eq!(serde_qs::to_string(&duration).unwrap(), "duration=forever");
+    }
+
+    #[test]
+    fn serializes_requirements_with_minimum_amount() {
+        let requirements = PromoCodeRequirements {
+            first_time_transaction: true,
+            minimum_amount: Some(1000),
+            minimum_amount_currency: Some("usd".to_owned()),
+        };
+        assert_eq!(
+            serde_qs::to_string(&requirements).unwrap(),
+            "first_time_transaction=true&minimum_amount=1000&minimum_amount_currency=usd"
+        );
+    }
+
+    #[test]
+    fn serializes_requirements_without_minimum_amount() {
+        let requirements = PromoCodeRequirements::default();
+        assert_eq!(
+            serde_qs::to_string(&requirements).unwrap(),
+            "first_time_transaction=false"
+        );
+    }
+}
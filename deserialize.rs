@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer};
+
+/// Deserializes a field Stripe may encode as either a JSON number or a
+/// numeric string, into the target numeric type. Stripe doesn't document
+/// which numeric fields are string-wrapped, so this is applied defensively
+/// rather than only where it's currently been observed.
+pub fn deserialize_number_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+    T::Err: fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    match NumberOrString::<T>::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(string) => string.parse::<T>().map_err(DeError::custom),
+    }
+}
+
+/// Like [`deserialize_number_from_string`], but for fields Stripe may return
+/// as `null`, which become `None`.
+pub fn deserialize_optional_number_from_string<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+    T::Err: fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    match Option::<NumberOrString<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(number)) => Ok(Some(number)),
+        Some(NumberOrString::String(string)) => {
+            string.parse::<T>().map(Some).map_err(DeError::custom)
+        }
+    }
+}
+
+/// Deserializes a Unix timestamp (seconds) into a `DateTime<Local>`. Tolerant
+/// of string-wrapped timestamps via [`deserialize_number_from_string`], for
+/// the same reason numeric fields are: Stripe doesn't document which fields
+/// are string-wrapped.
+pub fn deserialize_datetime_from_timestamp<'de, D>(
+    deserializer: D,
+) -> Result<DateTime<Local>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp: i64 = deserialize_number_from_string(deserializer)?;
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .ok_or_else(|| DeError::custom(format!("invalid timestamp: {}", timestamp)))
+}
+
+/// Like [`deserialize_datetime_from_timestamp`], but for fields Stripe may
+/// return as `null` (e.g. a coupon with no `redeem_by`).
+pub fn deserialize_optional_datetime_from_timestamp<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Local>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match deserialize_optional_number_from_string::<D, i64>(deserializer)? {
+        None => Ok(None),
+        Some(timestamp) => Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(Some)
+            .ok_or_else(|| DeError::custom(format!("invalid timestamp: {}", timestamp))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct NumberField {
+        #[serde(deserialize_with = "deserialize_number_from_string")]
+        value: i64,
+    }
+
+    #[test]
+    fn deserializes_number_from_number() {
+        let parsed: NumberField = serde_json::from_value(json!({ "value": 42 })).unwrap();
+        assert_eq!(parsed.value, 42);
+    }
+
+    #[test]
+    fn deserializes_number_from_string() {
+        let parsed: NumberField = serde_json::from_value(json!({ "value": "42" })).unwrap();
+        assert_eq!(parsed.value, 42);
+    }
+
+    #[derive(Deserialize)]
+    struct OptionalNumberField {
+        #[serde(deserialize_with = "deserialize_optional_number_from_string")]
+        value: Option<i64>,
+    }
+
+    #[test]
+    fn deserializes_optional_number_from_null() {
+        let parsed: OptionalNumberField = serde_json::from_value(json!({ "value": null })).unwrap();
+        assert_eq!(parsed.value, None);
+    }
+
+    #[derive(Deserialize)]
+    struct DatetimeField {
+        #[serde(deserialize_with = "deserialize_datetime_from_timestamp")]
+        value: DateTime<Local>,
+    }
+
+    #[test]
+    fn deserializes_datetime_from_timestamp_number() {
+        let parsed: DatetimeField = serde_json::from_value(json!({ "value": 0 })).unwrap();
+        assert_eq!(parsed.value.timestamp(), 0);
+    }
+
+    #[test]
+    fn deserializes_datetime_from_timestamp_string() {
+        let parsed: DatetimeField = serde_json::from_value(json!({ "value": "0" })).unwrap();
+        assert_eq!(parsed.value.timestamp(), 0);
+    }
+
+    #[derive(Deserialize)]
+    struct OptionalDatetimeField {
+        #[serde(deserialize_with = "deserialize_optional_datetime_from_timestamp")]
+        value: Option<DateTime<Local>>,
+    }
+
+    #[test]
+    fn deserializes_optional_datetime_from_null() {
+        let parsed: OptionalDatetimeField =
+            serde_json::from_value(json!({ "value": null })).unwrap();
+        assert!(parsed.value.is_none());
+    }
+}
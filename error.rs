@@ -0,0 +1,74 @@
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Stripe's error envelope, e.g. `{"error": {"type", "code", "message", "param"}}`.
+#[derive(Deserialize, Debug)]
+pub struct StripeErrorBody {
+    pub error: StripeErrorDetail,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StripeErrorDetail {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub code: Option<String>,
+    pub message: String,
+    pub param: Option<String>,
+}
+
+/// A Stripe API response body, which is either the requested payload or an
+/// error envelope, distinguished structurally rather than by status code
+/// alone (mirroring the Success/Error response-body split used by
+/// receipt-validation clients).
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum StripeResponse<T> {
+    Success(T),
+    ApiError(StripeErrorBody),
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("unauthorized: check your Stripe API key")]
+    Unauthorized,
+    #[error("{message}")]
+    BadRequest {
+        code: Option<String>,
+        message: String,
+        param: Option<String>,
+    },
+    #[error("rate limited by Stripe")]
+    RateLimited,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("cannot parse date: {0}")]
+    DateParse(#[from] chrono::ParseError),
+}
+
+/// Turns a raw Stripe HTTP response into a typed result, surfacing
+/// Stripe-provided `message`/`code` instead of a generic string.
+pub async fn parse_stripe_response<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+    let status = response.status();
+
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(Error::Unauthorized);
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(Error::RateLimited);
+    }
+
+    let bytes = response.bytes().await?;
+    match serde_json::from_slice(&bytes)? {
+        StripeResponse::Success(value) => Ok(value),
+        StripeResponse::ApiError(body) => Err(Error::BadRequest {
+            code: body.error.code,
+            message: body.error.message,
+            param: body.error.param,
+        }),
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// File format for the generated vouchers.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "lowercase")]
+pub enum OutputFormat {
+    PlainText,
+    Csv,
+    Json,
+}
+
+/// A single generated voucher plus the coupon context it was minted under,
+/// suitable for import into downstream systems and spreadsheets.
+#[derive(Serialize, Debug)]
+pub struct VoucherRecord {
+    pub code: String,
+    pub coupon_id: String,
+    pub product_id: String,
+    pub percent_off: Option<f32>,
+    pub amount_off: Option<i64>,
+    pub currency: Option<String>,
+    pub expires_at: String,
+    pub max_redemptions: i32,
+    pub first_time_transaction: bool,
+}
+
+/// Writes voucher records to disk one at a time, so a run interrupted
+/// partway through still leaves a valid, importable file.
+///
+/// JSON output is newline-delimited (one record per line) rather than a
+/// single array for the same reason: a truncated array is invalid JSON, but
+/// a truncated stream of complete lines is not.
+pub struct VoucherWriter {
+    format: OutputFormat,
+    file: File,
+    wrote_header: bool,
+}
+
+impl VoucherWriter {
+    pub fn create(path: &str, format: OutputFormat) -> io::Result<Self> {
+        Ok(Self {
+            format,
+            file: File::create(path)?,
+            wrote_header: false,
+        })
+    }
+
+    pub fn write_record(&mut self, record: &VoucherRecord) -> io::Result<()> {
+        match self.format {
+            OutputFormat::PlainText => writeln!(self.file, "{}", record.code),
+            OutputFormat::Csv => {
+                if !self.wrote_header {
+                    writeln!(
+                        self.file,
+                        "code,coupon_id,product_id,percent_off,amount_off,currency,expires_at,max_redemptions,first_time_transaction"
+                    )?;
+                    self.wrote_header = true;
+                }
+                writeln!(
+                    self.file,
+                    "{},{},{},{},{},{},{},{},{}",
+                    record.code,
+                    record.coupon_id,
+                    record.product_id,
+                    record.percent_off.map_or(String::new(), |v| v.to_string()),
+                    record.amount_off.map_or(String::new(), |v| v.to_string()),
+                    record.currency.as_deref().unwrap_or(""),
+                    record.expires_at,
+                    record.max_redemptions,
+                    record.first_time_transaction,
+                )
+            }
+            OutputFormat::Json => {
+                let line = serde_json::to_string(record)
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+                writeln!(self.file, "{}", line)
+            }
+        }
+    }
+}
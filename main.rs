@@ -1,19 +1,40 @@
+mod cli;
+mod coupon;
+mod deserialize;
+mod error;
+mod export;
+mod retry;
+
 use chrono::{offset::TimeZone, DateTime, Local, NaiveDateTime};
+use clap::Parser;
+use cli::Cli;
+use coupon::{
+    CouponAppliesTo, CouponDuration, CouponDurationKind, CouponRequest, CouponStatus,
+    DiscountValue, PromoCodeRequirements, PromotionCode, PromotionCodeRequest,
+};
+use deserialize::{
+    deserialize_datetime_from_timestamp, deserialize_number_from_string,
+    deserialize_optional_datetime_from_timestamp, deserialize_optional_number_from_string,
+};
+use error::{parse_stripe_response, Error};
+use export::{VoucherRecord, VoucherWriter};
 use reqwest::{
     self,
     header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    Client,
 };
+use retry::{execute_with_strategy, generate_idempotency_key, RequestStrategy};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
+use std::time::Duration;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Product {
     id: String,
     object: String,
     active: bool,
-    created: i32,
+    #[serde(deserialize_with = "deserialize_datetime_from_timestamp")]
+    created: DateTime<Local>,
     default_price: Option<String>,
     description: Option<String>,
     images: Vec<String>,
@@ -25,7 +46,8 @@ struct Product {
     statement_descriptor: Option<String>,
     tax_code: Option<String>,
     unit_label: Option<String>,
-    updated: i32,
+    #[serde(deserialize_with = "deserialize_datetime_from_timestamp")]
+    updated: DateTime<Local>,
     url: Option<String>,
 }
 
@@ -41,46 +63,41 @@ struct ProductsResponse {
 struct Coupon {
     id: String,
     object: String,
+    #[serde(deserialize_with = "deserialize_optional_number_from_string")]
     amount_off: Option<i32>,
-    created: i32,
+    #[serde(deserialize_with = "deserialize_datetime_from_timestamp")]
+    created: DateTime<Local>,
     currency: Option<String>,
     duration: String,
     duration_in_months: Option<i32>,
     livemode: bool,
+    #[serde(deserialize_with = "deserialize_optional_number_from_string")]
     max_redemptions: Option<i32>,
     metadata: HashMap<String, String>,
     name: Option<String>,
-    percent_off: f32,
-    redeem_by: Option<i32>,
+    #[serde(deserialize_with = "deserialize_optional_number_from_string")]
+    percent_off: Option<f32>,
+    #[serde(deserialize_with = "deserialize_optional_datetime_from_timestamp")]
+    redeem_by: Option<DateTime<Local>>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
     times_redeemed: i32,
     valid: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct CouponRequest {
-    name: String,
-    percent_off: f32,
-    redeem_by: i64,
-    applies_to: CouponAppliesTo,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct CouponAppliesTo {
-    products: Vec<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct PromotionCodeRequest {
-    coupon: String,
-    code: String,
-    expires_at: i64,
-    max_redemptions: i32,
-    restrictions: PromotionCodeRestrictions,
-}
+impl Coupon {
+    /// Whether this coupon can still be redeemed: it must not have been
+    /// invalidated by Stripe, and if it has a `redeem_by` date, that date
+    /// must not have passed.
+    fn status(&self) -> CouponStatus {
+        if !self.valid {
+            return CouponStatus::Invalidated;
+        }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PromotionCodeRestrictions {
-    first_time_transaction: bool,
+        match self.redeem_by {
+            Some(redeem_by) if redeem_by < Local::now() => CouponStatus::Expired,
+            _ => CouponStatus::Valid,
+        }
+    }
 }
 
 fn generate_random_code() -> String {
@@ -98,28 +115,148 @@ fn generate_random_code() -> String {
     return rand_str;
 }
 
+async fn fetch_products_page(
+    client: &Client,
+    stripe_key: &str,
+    starting_after: Option<&str>,
+) -> Result<ProductsResponse, Error> {
+    let strategy = RequestStrategy {
+        max_retries: 5,
+        base_delay: Duration::from_millis(200),
+        idempotency_key: None,
+    };
+    let response = execute_with_strategy(&strategy, || {
+        let mut request = client
+            .get("https://api.stripe.com/v1/products")
+            .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
+            .header(ACCEPT, "application/json")
+            .query(&[("active", "true")]);
+        if let Some(starting_after) = starting_after {
+            request = request.query(&[("starting_after", starting_after)]);
+        }
+        request
+    })
+    .await?;
+
+    parse_stripe_response(response).await
+}
+
+/// Fetches every active product, following `has_more`/`starting_after`
+/// pagination until Stripe reports no more pages.
+async fn fetch_all_products(client: &Client, stripe_key: &str) -> Result<Vec<Product>, Error> {
+    let mut products = Vec::new();
+    let mut starting_after: Option<String> = None;
+
+    loop {
+        let page = fetch_products_page(client, stripe_key, starting_after.as_deref()).await?;
+        let has_more = page.has_more;
+        starting_after = page.data.last().map(|product| product.id.clone());
+        products.extend(page.data);
+
+        if !has_more || starting_after.is_none() {
+            break;
+        }
+    }
+
+    Ok(products)
+}
+
+async fn create_coupon(
+    client: &Client,
+    stripe_key: &str,
+    coupon_request: &CouponRequest,
+) -> Result<Coupon, Error> {
+    let body = serde_qs::to_string(coupon_request).map_err(|err| Error::BadRequest {
+        code: None,
+        message: format!("cannot encode coupon request: {}", err),
+        param: None,
+    })?;
+
+    let strategy = RequestStrategy {
+        max_retries: 5,
+        base_delay: Duration::from_millis(200),
+        idempotency_key: Some(generate_idempotency_key()),
+    };
+    let response = execute_with_strategy(&strategy, || {
+        client
+            .post("https://api.stripe.com/v1/coupons")
+            .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body.clone())
+    })
+    .await?;
+
+    parse_stripe_response(response).await
+}
+
+async fn create_promotion_code(
+    client: &Client,
+    stripe_key: &str,
+    promotion_code_request: &PromotionCodeRequest,
+) -> Result<PromotionCode, Error> {
+    let body = serde_qs::to_string(promotion_code_request).map_err(|err| Error::BadRequest {
+        code: None,
+        message: format!("cannot encode promotion code request: {}", err),
+        param: None,
+    })?;
+
+    let strategy = RequestStrategy {
+        max_retries: 5,
+        base_delay: Duration::from_millis(200),
+        idempotency_key: Some(generate_idempotency_key()),
+    };
+    let response = execute_with_strategy(&strategy, || {
+        client
+            .post("https://api.stripe.com/v1/promotion_codes")
+            .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body.clone())
+    })
+    .await?;
+
+    parse_stripe_response(response).await
+}
+
 #[tokio::main]
 async fn main() {
     println!("[ Rusty Voucher ]");
 
-    let first_time_transaction = false;
+    let cli = Cli::parse();
 
-    let mut stripe_key = String::new();
-    println!("Enter your Stripe key:");
-    std::io::stdin().read_line(&mut stripe_key).unwrap();
-    stripe_key = stripe_key.trim().to_owned();
+    let first_time_transaction = cli.first_time_transaction;
 
-    // Input coupon name
-    let mut coupon_name = String::new();
-    println!("Coupon name: ");
-    std::io::stdin().read_line(&mut coupon_name).unwrap();
+    let stripe_key = match cli.stripe_api_key {
+        Some(key) => key,
+        None => {
+            let mut stripe_key = String::new();
+            println!("Enter your Stripe key:");
+            std::io::stdin().read_line(&mut stripe_key).unwrap();
+            stripe_key.trim().to_owned()
+        }
+    };
+
+    let coupon_name = match cli.coupon_name {
+        Some(name) => name,
+        None => {
+            let mut coupon_name = String::new();
+            println!("Coupon name: ");
+            std::io::stdin().read_line(&mut coupon_name).unwrap();
+            coupon_name.trim().to_owned()
+        }
+    };
 
     // Input expiration date
-    let mut expiration_string = String::new();
-    println!("Expiration date (YYYY-MM-DD):");
-    std::io::stdin().read_line(&mut expiration_string).unwrap();
+    let expiration_string = match cli.expires {
+        Some(expires) => expires,
+        None => {
+            let mut expiration_string = String::new();
+            println!("Expiration date (YYYY-MM-DD):");
+            std::io::stdin().read_line(&mut expiration_string).unwrap();
+            expiration_string.trim().to_owned()
+        }
+    };
     let parse_result = NaiveDateTime::parse_from_str(
-        &format!("{} 23:59:59", &expiration_string.trim()),
+        &format!("{} 23:59:59", expiration_string.trim()),
         "%Y-%m-%d %H:%M:%S",
     );
     let expiration_date: DateTime<Local> = match parse_result {
@@ -130,17 +267,21 @@ async fn main() {
         }
     };
 
-    let mut requested_code_count_string = String::new();
-    println!("How many codes do you need:");
-    std::io::stdin()
-        .read_line(&mut requested_code_count_string)
-        .unwrap();
-    let parsed_requested_code_count = requested_code_count_string.trim().parse::<i32>();
-    let requested_code_count = match parsed_requested_code_count {
-        Ok(result) => result,
-        Err(_) => {
-            println!("Cannot parse number of vouchers. Aborting.");
-            return;
+    let requested_code_count = match cli.count {
+        Some(count) => count,
+        None => {
+            let mut requested_code_count_string = String::new();
+            println!("How many codes do you need:");
+            std::io::stdin()
+                .read_line(&mut requested_code_count_string)
+                .unwrap();
+            match requested_code_count_string.trim().parse::<i32>() {
+                Ok(result) => result,
+                Err(_) => {
+                    println!("Cannot parse number of vouchers. Aborting.");
+                    return;
+                }
+            }
         }
     };
 
@@ -149,146 +290,218 @@ async fn main() {
         return;
     }
 
-    let client = reqwest::Client::new();
-    let product_response = client
-        .get("https://api.stripe.com/v1/products")
-        .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
-        .header(ACCEPT, "application/json")
-        .send()
-        .await
-        .unwrap();
-
-    if product_response.status() == reqwest::StatusCode::UNAUTHORIZED {
-        println!("Unauthorized: Probably wrong stripe key");
-        return;
-    }
+    let client = Client::new();
+    let all_products = match fetch_all_products(&client, &stripe_key).await {
+        Ok(products) => products,
+        Err(err) => {
+            println!("Cannot fetch products: {}", err);
+            return;
+        }
+    };
 
-    if product_response.status() != reqwest::StatusCode::OK {
-        println!("Unexpected error");
+    if all_products.is_empty() {
+        println!("No available products");
         return;
     }
 
-    let data: ProductsResponse = product_response.json().await.unwrap();
-
-    println!("Select a product from list:");
-
-    let mut products: Vec<String> = Vec::new();
-    let mut i = 0;
+    let selected_product_id = match cli.product_id {
+        Some(product_id) => {
+            if !all_products.iter().any(|item| item.id == product_id) {
+                println!("Invalid product selected");
+                return;
+            }
+            product_id
+        }
+        None => {
+            let name_filter = match cli.product_name {
+                Some(filter) => filter,
+                None => {
+                    let mut name_filter_string = String::new();
+                    println!("Filter products by name (optional, press enter to show all):");
+                    std::io::stdin()
+                        .read_line(&mut name_filter_string)
+                        .unwrap();
+                    name_filter_string.trim().to_owned()
+                }
+            };
+
+            let filtered_products: Vec<&Product> = all_products
+                .iter()
+                .filter(|item| {
+                    name_filter.is_empty()
+                        || item
+                            .name
+                            .to_lowercase()
+                            .contains(&name_filter.to_lowercase())
+                })
+                .collect();
+
+            if filtered_products.is_empty() {
+                println!("No products match that filter");
+                return;
+            }
+
+            println!("Select a product from list:");
+            for (i, item) in filtered_products.iter().enumerate() {
+                println!("[{}] {}", i, item.name);
+            }
+
+            let mut requested_product_id_string = String::new();
+            std::io::stdin()
+                .read_line(&mut requested_product_id_string)
+                .unwrap();
+            let requested_product_id = match requested_product_id_string.trim().parse::<usize>() {
+                Ok(result) => result,
+                Err(_) => {
+                    println!("Cannot parse selected ID of product. Aborting.");
+                    return;
+                }
+            };
+
+            match filtered_products.get(requested_product_id) {
+                Some(product) => product.id.clone(),
+                None => {
+                    println!("Invalid product selected");
+                    return;
+                }
+            }
+        }
+    };
 
-    for item in data.data {
-        products.push(item.id.clone());
-        println!("[{}] {}", i, item.name);
-        i += 1;
-    }
+    // Discount type. Defaults to the original 100%-off behavior when neither
+    // --percent-off nor --amount-off is given, so a scripted run is never
+    // blocked on stdin waiting for input this request didn't ask for.
+    let discount = if let Some(percent_off) = cli.percent_off {
+        DiscountValue::Percentage { percent_off }
+    } else if let Some(amount_off) = cli.amount_off {
+        let currency = match &cli.currency {
+            Some(currency) => currency.to_lowercase(),
+            None => {
+                println!("--currency is required when --amount-off is set. Aborting.");
+                return;
+            }
+        };
+        DiscountValue::AmountOff {
+            amount_off,
+            currency,
+        }
+    } else {
+        DiscountValue::Percentage { percent_off: 100.0 }
+    };
 
-    if products.is_empty() {
-        println!("No available products");
-        return;
-    }
+    // Optional minimum cart value. Defaults to none when --minimum-amount is
+    // not given, for the same non-interactive reason as the discount above.
+    let (minimum_amount, minimum_amount_currency) = if let Some(minimum_amount) = cli.minimum_amount
+    {
+        let currency = match &cli.currency {
+            Some(currency) => currency.to_lowercase(),
+            None => {
+                println!("--currency is required when --minimum-amount is set. Aborting.");
+                return;
+            }
+        };
+        (Some(minimum_amount), Some(currency))
+    } else {
+        (None, None)
+    };
 
-    let mut requested_product_id_string = String::new();
-    std::io::stdin()
-        .read_line(&mut requested_product_id_string)
-        .unwrap();
-    let parsed_requested_product_id = requested_product_id_string.trim().parse::<usize>();
-    let requested_product_id = match parsed_requested_product_id {
-        Ok(result) => result,
-        Err(_) => {
-            println!("Cannot parse selected ID of product. Aborting.");
-            return;
+    // Coupon duration. Defaults to `once`, the original hardcoded behavior.
+    let duration = match cli.duration.unwrap_or(CouponDurationKind::Once) {
+        CouponDurationKind::Once => CouponDuration::Once,
+        CouponDurationKind::Forever => CouponDuration::Forever,
+        CouponDurationKind::Repeating => {
+            let duration_in_months = match cli.duration_in_months {
+                Some(duration_in_months) => duration_in_months,
+                None => {
+                    println!(
+                        "--duration-in-months is required when --duration repeating is set. Aborting."
+                    );
+                    return;
+                }
+            };
+            CouponDuration::Repeating { duration_in_months }
         }
     };
 
-    if requested_product_id > products.len() {
-        println!("Invalid product selected");
-        return;
-    }
+    let (percent_off, amount_off, currency) = match &discount {
+        DiscountValue::Percentage { percent_off } => (Some(*percent_off), None, None),
+        DiscountValue::AmountOff {
+            amount_off,
+            currency,
+        } => (None, Some(*amount_off), Some(currency.clone())),
+    };
 
     print!("Creating a coupon...");
 
     let coupon_request = CouponRequest {
         name: coupon_name.trim().to_owned(),
-        percent_off: 100.0,
+        discount,
+        duration,
+        max_redemptions: cli.max_redemptions,
         redeem_by: expiration_date.timestamp(),
         applies_to: CouponAppliesTo {
-            products: vec![products[requested_product_id].clone()],
+            products: vec![selected_product_id.clone()],
         },
     };
 
-    let coupon_request_body = serde_qs::to_string(&coupon_request).unwrap();
-
-    let coupon_response = client
-        .post("https://api.stripe.com/v1/coupons")
-        .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
-        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .body(coupon_request_body)
-        .send()
-        .await
-        .unwrap();
-
-    if coupon_response.status() == reqwest::StatusCode::UNAUTHORIZED {
-        println!("Unauthorized: Probably wrong stripe key");
-        return;
-    }
+    let coupon = match create_coupon(&client, &stripe_key, &coupon_request).await {
+        Ok(coupon) => coupon,
+        Err(err) => {
+            println!("Coupon cannot be created: {}", err);
+            return;
+        }
+    };
 
-    if coupon_response.status() == reqwest::StatusCode::BAD_REQUEST {
-        println!("Coupon cannot be created");
-        return;
-    }
+    println!("[ DONE ]");
 
-    if coupon_response.status() != reqwest::StatusCode::OK {
-        println!("Unexpected error");
-        return;
+    match coupon.status() {
+        CouponStatus::Valid => {}
+        CouponStatus::Expired => {
+            println!("Coupon already expired. Aborting.");
+            return;
+        }
+        CouponStatus::Invalidated => {
+            println!("Coupon was not created as valid by Stripe. Aborting.");
+            return;
+        }
     }
 
-    let coupon: Coupon = coupon_response.json().await.unwrap();
-
-    println!("[ DONE ]");
-
     //
     // Create promotion codes
     //
     let mut created_code_count = 0;
 
-    let mut file = File::create("vouchers.txt").unwrap();
+    let mut writer = VoucherWriter::create(&cli.output, cli.format).unwrap();
 
     while created_code_count < requested_code_count {
-        let mut restrictions = HashMap::new();
-        restrictions.insert(String::from("first_time_transaction"), String::from("true"));
-
         let promotion_code_request = PromotionCodeRequest {
             coupon: coupon.id.clone(),
             code: generate_random_code(),
             expires_at: expiration_date.timestamp(),
             max_redemptions: 1,
-            restrictions: PromotionCodeRestrictions {
+            restrictions: PromoCodeRequirements {
                 first_time_transaction,
+                minimum_amount,
+                minimum_amount_currency: minimum_amount_currency.clone(),
             },
         };
 
-        let promotion_code_request_body = serde_qs::to_string(&promotion_code_request).unwrap();
-
-        let promotion_code_response = client
-            .post("https://api.stripe.com/v1/promotion_codes")
-            .header(AUTHORIZATION, format!("Bearer {}", stripe_key))
-            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-            .body(promotion_code_request_body)
-            .send()
-            .await
-            .unwrap();
-
-        if promotion_code_response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            println!("Unauthorized: Probably wrong stripe key");
-            break;
-        }
-
-        if promotion_code_response.status() == reqwest::StatusCode::BAD_REQUEST {
-            continue;
-        }
-
-        if promotion_code_response.status() != reqwest::StatusCode::OK {
-            println!("Unexpected error");
-            break;
+        match create_promotion_code(&client, &stripe_key, &promotion_code_request).await {
+            Ok(_) => {}
+            // A collision with an existing code: generate another one and retry.
+            Err(Error::BadRequest { code, .. })
+                if code.as_deref() == Some("resource_already_exists") =>
+            {
+                continue;
+            }
+            Err(Error::Unauthorized) => {
+                println!("Unauthorized: Probably wrong stripe key");
+                break;
+            }
+            Err(err) => {
+                println!("Promotion code cannot be created: {}", err);
+                break;
+            }
         }
 
         created_code_count += 1;
@@ -296,6 +509,18 @@ async fn main() {
             "Promotion code {} or {} [{}]",
             created_code_count, requested_code_count, promotion_code_request.code
         );
-        writeln!(&mut file, "{}", promotion_code_request.code).unwrap();
+
+        let record = VoucherRecord {
+            code: promotion_code_request.code,
+            coupon_id: coupon.id.clone(),
+            product_id: selected_product_id.clone(),
+            percent_off,
+            amount_off,
+            currency: currency.clone(),
+            expires_at: expiration_date.to_rfc3339(),
+            max_redemptions: promotion_code_request.max_redemptions,
+            first_time_transaction,
+        };
+        writer.write_record(&record).unwrap();
     }
 }
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+/// How a single logical Stripe call should be executed: retried on
+/// transient failures with exponential backoff and jitter, optionally
+/// attaching an `Idempotency-Key` so retries are safe to replay against
+/// Stripe without double-charging or double-creating.
+///
+/// Borrowed from async-stripe's client design: callers pick a strategy once
+/// per call site instead of hand-rolling retry loops around every request.
+#[derive(Clone, Debug)]
+pub struct RequestStrategy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub idempotency_key: Option<String>,
+}
+
+/// What to do next after inspecting a response's HTTP status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The status is transient; try again if the strategy allows it.
+    Retry,
+    /// The status is a definitive success; stop and return it.
+    Stop,
+    /// The status is a definitive failure; stop and let the caller inspect it.
+    Continue,
+}
+
+/// Classifies a Stripe HTTP status into a retry decision.
+fn classify_status(status: StatusCode) -> Outcome {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::INTERNAL_SERVER_ERROR
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE => Outcome::Retry,
+        StatusCode::OK => Outcome::Stop,
+        _ => Outcome::Continue,
+    }
+}
+
+/// Generates a fresh idempotency key for a single logical operation.
+///
+/// Stripe only requires the key be unique per operation, so a random token
+/// is enough — it does not need to be a UUID.
+pub fn generate_idempotency_key() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz1234567890";
+    const STR_LEN: usize = 32;
+    let mut rng = rand::thread_rng();
+
+    (0..STR_LEN)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+/// Executes an HTTP request according to `strategy`, retrying on retryable
+/// statuses (429, 500, 502, 503) with `base_delay * 2^attempt` plus jitter,
+/// up to `max_retries` attempts.
+///
+/// `build_request` is called once per attempt so it can rebuild the body
+/// (reqwest's `RequestBuilder` is not `Clone`-and-resend friendly once a
+/// body has been set).
+pub async fn execute_with_strategy<F>(
+    strategy: &RequestStrategy,
+    mut build_request: F,
+) -> Result<Response, reqwest::Error>
+where
+    F: FnMut() -> RequestBuilder,
+{
+    let idempotency_key = strategy.idempotency_key.as_deref();
+
+    let mut attempt: u32 = 0;
+    loop {
+        let mut request = build_request();
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+
+        let response = request.send().await?;
+
+        if classify_status(response.status()) == Outcome::Retry && attempt < strategy.max_retries {
+            let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..250));
+            let delay = strategy.base_delay * 2u32.pow(attempt) + jitter;
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            continue;
+        }
+
+        return Ok(response);
+    }
+}